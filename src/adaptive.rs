@@ -0,0 +1,49 @@
+//! Adaptive sampling harness: keeps drawing batches of samples from a
+//! test case until the relative standard error of the mean is known to
+//! be small, rather than running a single hardcoded iteration count that
+//! over-runs cheap cases and may under-sample noisy ones.
+
+use std::time::{Duration, Instant};
+
+use crate::stats;
+
+const BATCH_SIZE: u32 = 200;
+
+/// Run `sample` in batches of `BATCH_SIZE`, stopping once `min_time` has
+/// elapsed and the relative standard error of the mean drops below
+/// `threshold`, or once `max_iterations` samples have been collected,
+/// whichever comes first. Returns the samples sorted ascending.
+pub fn run(
+    min_time: Duration,
+    threshold: f64,
+    max_iterations: u32,
+    mut sample: impl FnMut() -> u64,
+) -> Vec<u64> {
+    let mut results = Vec::new();
+    let start = Instant::now();
+
+    loop {
+        for _ in 0..BATCH_SIZE {
+            results.push(sample());
+            if results.len() as u32 >= max_iterations {
+                break;
+            }
+        }
+
+        if results.len() as u32 >= max_iterations {
+            break;
+        }
+
+        if start.elapsed() >= min_time {
+            let mean = stats::mean_cycles(&results);
+            let stddev = stats::stddev_cycles(&results, mean);
+            let rse = stats::relative_standard_error(mean, stddev, results.len());
+            if rse < threshold {
+                break;
+            }
+        }
+    }
+
+    results.sort();
+    results
+}
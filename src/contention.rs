@@ -0,0 +1,79 @@
+//! Detects scheduler/interrupt contention on the pinned core via
+//! `/proc/stat`, so a high p99 can be told apart from genuine sleep
+//! overhead versus a dirty machine stealing time from the benchmark.
+
+use std::path::PathBuf;
+
+/// Above this percentage of non-idle, non-benchmark time on the pinned
+/// core during a measurement window, warn that results may be noisy.
+const CONTENTION_WARN_THRESHOLD_PCT: f64 = 5.0;
+
+struct CpuTimes {
+    system: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+    total: u64,
+}
+
+/// Parse the `cpuN user nice system idle iowait irq softirq steal ...`
+/// line for `core` out of `/proc/stat`. All values are cumulative
+/// jiffies since boot.
+fn read_cpu_times(core: u32) -> Option<CpuTimes> {
+    let stat = std::fs::read_to_string(PathBuf::from("/proc/stat")).ok()?;
+    let prefix = format!("cpu{} ", core);
+    let line = stat.lines().find(|line| line.starts_with(&prefix))?;
+
+    let fields = line
+        .split_whitespace()
+        .skip(1)
+        .map(|f| f.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    if fields.len() < 8 {
+        return None;
+    }
+
+    let total = fields.iter().sum();
+    Some(CpuTimes {
+        system: fields[2],
+        irq: fields[5],
+        softirq: fields[6],
+        steal: fields[7],
+        total,
+    })
+}
+
+/// Percentage of the measurement window the pinned core spent in
+/// system/irq/softirq/steal time rather than idling or running the
+/// benchmark thread itself.
+fn contention_pct(before: &CpuTimes, after: &CpuTimes) -> f64 {
+    let total_delta = after.total.saturating_sub(before.total);
+    if total_delta == 0 {
+        return 0.0;
+    }
+
+    let contended_delta = (after.system + after.irq + after.softirq + after.steal)
+        .saturating_sub(before.system + before.irq + before.softirq + before.steal);
+
+    contended_delta as f64 / total_delta as f64 * 100.0
+}
+
+/// Run `f`, measuring the contention percentage on `core` over its
+/// execution. `None` if `/proc/stat` could not be read or parsed.
+pub fn measure<T>(core: u32, f: impl FnOnce() -> T) -> (T, Option<f64>) {
+    let before = read_cpu_times(core);
+    let result = f();
+    let after = read_cpu_times(core);
+
+    let pct = match (before, after) {
+        (Some(before), Some(after)) => Some(contention_pct(&before, &after)),
+        _ => None,
+    };
+
+    (result, pct)
+}
+
+pub fn is_high(contention_pct: f64) -> bool {
+    contention_pct > CONTENTION_WARN_THRESHOLD_PCT
+}
@@ -0,0 +1,55 @@
+//! RAPL (Running Average Power Limit) energy measurement via the
+//! powercap sysfs interface, so a run can report average watts alongside
+//! latency for `hr_sleep` (busy-spin) vs `nanosleep` (lets the core idle).
+
+use std::path::PathBuf;
+
+const RAPL_PACKAGE_DOMAIN: &str = "/sys/class/powercap/intel-rapl:0";
+
+fn read_u64(file: &str) -> Option<u64> {
+    std::fs::read_to_string(PathBuf::from(RAPL_PACKAGE_DOMAIN).join(file))
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+}
+
+fn read_energy_uj() -> Option<u64> {
+    read_u64("energy_uj")
+}
+
+fn read_max_energy_range_uj() -> Option<u64> {
+    read_u64("max_energy_range_uj")
+}
+
+/// `energy_uj` is a monotonically increasing counter that wraps at
+/// `max_energy_range_uj`, so the delta has to be taken modulo that range.
+fn energy_delta_uj(before: u64, after: u64, max_range_uj: u64) -> u64 {
+    if after >= before {
+        after - before
+    } else {
+        after + max_range_uj - before
+    }
+}
+
+/// Run `f`, measuring the RAPL package energy it consumed, and return its
+/// result alongside the average power draw in watts. `None` when the
+/// powercap directory is absent or unreadable (e.g. due to permissions),
+/// so callers should report "energy: unavailable" rather than a number.
+pub fn measure_watts<T>(f: impl FnOnce() -> T) -> (T, Option<f64>) {
+    let before = read_energy_uj();
+    let start = std::time::Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let after = read_energy_uj();
+
+    let watts = match (before, after, read_max_energy_range_uj()) {
+        (Some(before), Some(after), Some(max_range)) => {
+            let joules = energy_delta_uj(before, after, max_range) as f64 / 1_000_000.0;
+            Some(joules / elapsed.as_secs_f64())
+        }
+        _ => None,
+    };
+
+    (result, watts)
+}
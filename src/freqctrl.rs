@@ -0,0 +1,327 @@
+//! Frequency-pinning abstraction so the benchmark can run on both the
+//! legacy `acpi-cpufreq` driver (userspace governor + `scaling_setspeed`)
+//! and `intel_pstate`, which has no `userspace` governor and instead pins
+//! frequency by clamping `scaling_min_freq`/`scaling_max_freq` under the
+//! `performance` governor.
+
+use std::path::PathBuf;
+
+/// Remembers whatever a controller changed, so it can be put back.
+pub enum FreqState {
+    AcpiCpufreq { governor: String, freq: u32 },
+    IntelPstate { governor: String, min_freq: u32, max_freq: u32 },
+}
+
+pub trait FreqController {
+    /// Check that `freq_khz` can actually be pinned on `core`.
+    fn available(&self, core: u32, freq_khz: u32) -> bool;
+    /// Pin `core` to `freq_khz`, returning the previous state on success.
+    fn pin(&self, core: u32, freq_khz: u32) -> Option<FreqState>;
+    /// Undo a previous `pin`.
+    fn restore(&self, core: u32, state: FreqState);
+}
+
+/// Detect which `FreqController` applies to `core`, based on the driver
+/// reported in `scaling_driver`. Returns `None` for unsupported drivers.
+pub fn detect(core: u32) -> Option<Box<dyn FreqController>> {
+    let driver = std::fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_driver",
+        core
+    ))
+    .expect("Failed to open scaling_driver")
+    .trim()
+    .to_string();
+
+    if driver.eq_ignore_ascii_case("acpi-cpufreq") {
+        return Some(Box::new(AcpiCpufreq));
+    }
+
+    if driver.eq_ignore_ascii_case("intel_pstate") {
+        return Some(Box::new(IntelPstate));
+    }
+
+    None
+}
+
+pub struct AcpiCpufreq;
+
+impl FreqController for AcpiCpufreq {
+    fn available(&self, core: u32, freq: u32) -> bool {
+        let path = PathBuf::from(format!(
+            "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_available_frequencies",
+            core
+        ));
+
+        let raw_freqs =
+            std::fs::read_to_string(path).expect("Failed to open scaling_available_frequencies");
+
+        let freqs = raw_freqs
+            .split(" ")
+            .into_iter()
+            .filter(|s| {
+                if s.trim().is_empty() {
+                    return false;
+                }
+                return true;
+            })
+            .map(|s| match s.trim().parse::<u32>() {
+                Ok(freq) => freq,
+                Err(_) => {
+                    eprintln!(
+                        "Failed to parse scaling_available_frequencies, InvalidDigit `{}`",
+                        s
+                    );
+                    std::process::exit(1);
+                }
+            })
+            .collect::<Vec<u32>>();
+
+        for (i, available_freq) in freqs.iter().enumerate() {
+            let available_freq = *available_freq;
+            if available_freq == freq {
+                if i == 0 {
+                    return true;
+                }
+
+                let prev_freq = freqs[i - 1];
+                if available_freq - 1000 == prev_freq {
+                    eprintln!(
+                        "The frequency {} is not available, using the closest one {}",
+                        freq, prev_freq
+                    );
+                    return false;
+                } else {
+                    return true;
+                }
+            }
+        }
+
+        eprintln!("The frequency {} is not available", freq);
+        return false;
+    }
+
+    fn pin(&self, core: u32, freq: u32) -> Option<FreqState> {
+        let old_governor = std::fs::read_to_string(format!(
+            "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
+            core
+        ))
+        .expect("Failed to open scaling_governor")
+        .trim()
+        .to_string();
+
+        std::fs::write(
+            format!(
+                "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
+                core
+            ),
+            "userspace",
+        )
+        .expect("Failed to write scaling_governor");
+
+        let old_freq = match match std::fs::read_to_string(format!(
+            "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_setspeed",
+            core
+        )) {
+            Ok(freq) => freq,
+            Err(_) => {
+                eprintln!("Failed to open scaling_setspeed");
+                return None;
+            }
+        }
+        .trim()
+        .parse::<u32>()
+        {
+            Ok(freq) => freq,
+            Err(_) => {
+                eprintln!("Failed to parse scaling_setspeed");
+                return None;
+            }
+        };
+
+        match std::fs::write(
+            format!(
+                "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_setspeed",
+                core
+            ),
+            freq.to_string(),
+        ) {
+            Ok(_) => Some(FreqState::AcpiCpufreq {
+                governor: old_governor,
+                freq: old_freq,
+            }),
+            Err(_) => {
+                eprintln!("Failed to write scaling_setspeed");
+                None
+            }
+        }
+    }
+
+    fn restore(&self, core: u32, state: FreqState) {
+        let (governor, freq) = match state {
+            FreqState::AcpiCpufreq { governor, freq } => (governor, freq),
+            _ => return,
+        };
+
+        if std::fs::write(
+            format!(
+                "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_setspeed",
+                core
+            ),
+            freq.to_string(),
+        )
+        .is_err()
+        {
+            eprintln!("Failed to restore scaling_setspeed");
+        }
+
+        if std::fs::write(
+            format!(
+                "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
+                core
+            ),
+            governor,
+        )
+        .is_err()
+        {
+            eprintln!("Failed to restore scaling_governor");
+        }
+    }
+}
+
+pub struct IntelPstate;
+
+impl IntelPstate {
+    fn read_freq(core: u32, file: &str) -> Option<u32> {
+        std::fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cpufreq/{}", core, file))
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()
+    }
+
+    fn write_freq(core: u32, file: &str, freq: u32) -> bool {
+        std::fs::write(
+            format!("/sys/devices/system/cpu/cpu{}/cpufreq/{}", core, file),
+            freq.to_string(),
+        )
+        .is_ok()
+    }
+}
+
+impl FreqController for IntelPstate {
+    fn available(&self, core: u32, freq: u32) -> bool {
+        let min = match Self::read_freq(core, "cpuinfo_min_freq") {
+            Some(min) => min,
+            None => {
+                eprintln!("Failed to open cpuinfo_min_freq");
+                return false;
+            }
+        };
+        let max = match Self::read_freq(core, "cpuinfo_max_freq") {
+            Some(max) => max,
+            None => {
+                eprintln!("Failed to open cpuinfo_max_freq");
+                return false;
+            }
+        };
+
+        if freq < min || freq > max {
+            eprintln!(
+                "The frequency {} is outside the supported range [{}, {}]",
+                freq, min, max
+            );
+            return false;
+        }
+
+        true
+    }
+
+    fn pin(&self, core: u32, freq: u32) -> Option<FreqState> {
+        let old_governor = std::fs::read_to_string(format!(
+            "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
+            core
+        ))
+        .expect("Failed to open scaling_governor")
+        .trim()
+        .to_string();
+
+        let old_min = Self::read_freq(core, "scaling_min_freq")?;
+        let old_max = Self::read_freq(core, "scaling_max_freq")?;
+
+        std::fs::write(
+            format!(
+                "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
+                core
+            ),
+            "performance",
+        )
+        .expect("Failed to write scaling_governor");
+
+        // Widen to `freq` on whichever bound currently excludes it before
+        // clamping both to `freq`, so the core is never briefly asked for
+        // an invalid (min > max) range.
+        if freq < old_min {
+            if !Self::write_freq(core, "scaling_min_freq", freq) {
+                eprintln!("Failed to write scaling_min_freq");
+                return None;
+            }
+            if !Self::write_freq(core, "scaling_max_freq", freq) {
+                eprintln!("Failed to write scaling_max_freq");
+                return None;
+            }
+        } else {
+            if !Self::write_freq(core, "scaling_max_freq", freq) {
+                eprintln!("Failed to write scaling_max_freq");
+                return None;
+            }
+            if !Self::write_freq(core, "scaling_min_freq", freq) {
+                eprintln!("Failed to write scaling_min_freq");
+                return None;
+            }
+        }
+
+        Some(FreqState::IntelPstate {
+            governor: old_governor,
+            min_freq: old_min,
+            max_freq: old_max,
+        })
+    }
+
+    fn restore(&self, core: u32, state: FreqState) {
+        let (governor, min_freq, max_freq) = match state {
+            FreqState::IntelPstate {
+                governor,
+                min_freq,
+                max_freq,
+            } => (governor, min_freq, max_freq),
+            _ => return,
+        };
+
+        // The core is pinned at min == max. If that pinned value lies below
+        // the old `min_freq`, restore max first; otherwise restore min
+        // first. Either way the range never passes through min > max.
+        let pinned = Self::read_freq(core, "scaling_max_freq").unwrap_or(max_freq);
+        let order = if min_freq > pinned {
+            [("scaling_max_freq", max_freq), ("scaling_min_freq", min_freq)]
+        } else {
+            [("scaling_min_freq", min_freq), ("scaling_max_freq", max_freq)]
+        };
+        for (file, freq) in order {
+            if !Self::write_freq(core, file, freq) {
+                eprintln!("Failed to restore {}", file);
+            }
+        }
+
+        if std::fs::write(
+            format!(
+                "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
+                core
+            ),
+            governor,
+        )
+        .is_err()
+        {
+            eprintln!("Failed to restore scaling_governor");
+        }
+    }
+}
@@ -3,7 +3,14 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-const NUM_ITERATIONS: u32 = 10_000;
+mod adaptive;
+mod contention;
+mod energy;
+mod freqctrl;
+mod report;
+mod stats;
+
+use report::{BenchOptions, CaseResult, OutputFormat, Report};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -25,42 +32,66 @@ struct Args {
         help = "The frequency of the core in MHz"
     )]
     freq: u32,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "table",
+        help = "The output format of the benchmark result"
+    )]
+    format: OutputFormat,
+    #[arg(
+        long,
+        default_value_t = 100,
+        help = "Minimum time in milliseconds to sample a test case before checking for convergence"
+    )]
+    min_time: u64,
+    #[arg(
+        long,
+        default_value_t = 0.01,
+        help = "Target relative standard error of the mean (e.g. 0.01 = 1%) to stop adaptive sampling"
+    )]
+    threshold: f64,
+    #[arg(
+        long,
+        default_value_t = 100_000,
+        help = "Maximum number of iterations sampled per test case"
+    )]
+    max_iterations: u32,
 }
 
 fn main() {
     let arg = Args::parse();
 
     let mut setup_succ = true;
-    let mut old_governor = None;
-    let mut old_cpufreq = None;
+    let mut old_freq_state = None;
+    let mut old_turbo = None;
 
     if !core_available(arg.core) && setup_succ {
         eprintln!("The core {} is not available", arg.core);
         setup_succ = false;
     }
 
-    if !acpi_avaiable(arg.core) && setup_succ {
+    let controller = freqctrl::detect(arg.core);
+    if controller.is_none() && setup_succ {
         eprintln!(
-            "The scaling driver of core {} is not acpi-cpufreq",
+            "The scaling driver of core {} is not supported (expected acpi-cpufreq or intel_pstate)",
             arg.core
         );
         setup_succ = false;
     }
 
-    if !cpufreq_available(arg.core, arg.freq * 1000) && setup_succ {
+    if setup_succ && !controller.as_ref().unwrap().available(arg.core, arg.freq * 1000) {
         setup_succ = false;
     }
 
     if setup_succ {
-        old_governor = acpi_set_governor(arg.core, "userspace");
-        if old_governor.is_none() {
-            setup_succ = false;
-        }
+        // best-effort: a machine without turbo control should not abort the run.
+        old_turbo = turbo_set_disabled();
     }
 
     if setup_succ {
-        old_cpufreq = acpi_set_freq(arg.core, arg.freq * 1000);
-        if old_cpufreq.is_none() {
+        old_freq_state = controller.as_ref().unwrap().pin(arg.core, arg.freq * 1000);
+        if old_freq_state.is_none() {
             setup_succ = false;
         }
     }
@@ -81,56 +112,71 @@ fn main() {
                 std::time::Duration::from_micros(200),
             ];
 
-            println!("Benchmark Options: ");
-            println!("  Core: {}", arg.core);
-            println!("  Frequency: {} MHz", arg.freq);
-            println!("  Turbo boost: off");
-            println!("");
-
-            let mut case_results: Vec<(f64, f64, f64, f64)> = Vec::new();
             let tsc_per_micro = rtsc_time::cycles_per_sec() as f64 / 1_000_000.0;
+            let min_time = std::time::Duration::from_millis(arg.min_time);
+            let mut cases = Vec::new();
             for case in TEST_CASES {
-                let hr_sleep_results = hr_sleep_bench(case);
-                let nano_sleep_results = nano_sleep_bench(case);
-                let hr_sleep_results_mean = hr_sleep_results.iter().sum::<u64>() as f64
-                    / (hr_sleep_results.len() as f64 * tsc_per_micro);
-                let hr_sleep_results_99p =
-                    hr_sleep_results[hr_sleep_results.len() * 99 / 100] as f64 / tsc_per_micro;
-                let nano_sleep_results_mean = nano_sleep_results.iter().sum::<u64>() as f64
-                    / (nano_sleep_results.len() as f64 * tsc_per_micro);
-                let nano_sleep_results_99p =
-                    nano_sleep_results[nano_sleep_results.len() * 99 / 100] as f64 / tsc_per_micro;
-                case_results.push((
-                    hr_sleep_results_mean,
-                    hr_sleep_results_99p,
-                    nano_sleep_results_mean,
-                    nano_sleep_results_99p,
-                ));
+                let ((hr_sleep_results, hr_sleep_watts), hr_sleep_contention_pct) =
+                    contention::measure(arg.core, || {
+                        energy::measure_watts(|| {
+                            hr_sleep_bench(case, min_time, arg.threshold, arg.max_iterations)
+                        })
+                    });
+                let ((nano_sleep_results, nano_sleep_watts), nano_sleep_contention_pct) =
+                    contention::measure(arg.core, || {
+                        energy::measure_watts(|| {
+                            nano_sleep_bench(case, min_time, arg.threshold, arg.max_iterations)
+                        })
+                    });
+
+                for (label, contention_pct) in [
+                    ("hr_sleep", hr_sleep_contention_pct),
+                    ("nanosleep", nano_sleep_contention_pct),
+                ] {
+                    if let Some(pct) = contention_pct {
+                        if contention::is_high(pct) {
+                            eprintln!(
+                                "Warning: core {} was contended {:.1}% of the time during the {}µs {} case; p99 may reflect a dirty machine",
+                                arg.core,
+                                pct,
+                                case.as_micros(),
+                                label
+                            );
+                        }
+                    }
+                }
+
+                cases.push(CaseResult {
+                    case_micros: case.as_micros(),
+                    hr_sleep: stats::compute(&hr_sleep_results, tsc_per_micro),
+                    nano_sleep: stats::compute(&nano_sleep_results, tsc_per_micro),
+                    hr_sleep_watts,
+                    nano_sleep_watts,
+                    hr_sleep_contention_pct,
+                    nano_sleep_contention_pct,
+                });
             }
 
-            println!("Benchmark result: ");
-            println!("                hr_sleep              nanosleep");
-            for (i, case) in TEST_CASES.iter().enumerate() {
-                println!(
-                    "{}ns        {:.2}ns/{:.2}ns     {:.2}ns/{:.2}ns",
-                    case.as_micros(),
-                    case_results[i].0,
-                    case_results[i].1,
-                    case_results[i].2,
-                    case_results[i].3
-                );
-            }
+            let report = Report {
+                options: BenchOptions {
+                    core: arg.core,
+                    freq_mhz: arg.freq,
+                    turbo_off: old_turbo.is_some(),
+                },
+                cases,
+            };
+            report.print(arg.format);
 
             break;
         }
     }
 
-    if let Some(old_freq) = old_cpufreq {
-        acpi_set_freq(arg.core, old_freq);
+    if let Some(old_freq_state) = old_freq_state {
+        controller.unwrap().restore(arg.core, old_freq_state);
     }
 
-    if let Some(old_governor) = old_governor {
-        acpi_set_governor(arg.core, old_governor);
+    if let Some(old_turbo) = old_turbo {
+        turbo_restore(old_turbo);
     }
 
     if !setup_succ {
@@ -158,151 +204,87 @@ fn bind_core(core: u32) -> bool {
     }
 }
 
-fn acpi_avaiable(core: u32) -> bool {
-    let path = PathBuf::from(format!(
-        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_driver",
-        core
-    ));
-
-    std::fs::read_to_string(path)
-        .expect("Failed to open scaling_driver")
-        .trim()
-        .eq_ignore_ascii_case("acpi-cpufreq")
-}
-
-fn acpi_set_governor<S: AsRef<str>>(core: u32, new_governor: S) -> Option<String> {
-    let old_governor = std::fs::read_to_string(format!(
-        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
-        core
-    ))
-    .expect("Failed to open scaling_governor")
-    .trim()
-    .to_string();
-
-    std::fs::write(
-        format!(
-            "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor",
-            core
-        ),
-        new_governor.as_ref(),
-    )
-    .expect("Failed to write scaling_governor");
-
-    return Some(old_governor);
+/// Remembers which sysfs knob was used to disable turbo boost, and its
+/// previous value, so it can be put back the way we found it.
+enum TurboState {
+    IntelPstate(String),
+    GenericBoost(String),
 }
 
-fn acpi_set_freq(core: u32, new_freq: u32) -> Option<u32> {
-    let old_freq = match match std::fs::read_to_string(format!(
-        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_setspeed",
-        core
-    )) {
-        Ok(freq) => freq,
-        Err(_) => {
-            eprintln!("Failed to open scaling_setspeed");
-            return None;
-        }
-    }
-    .trim()
-    .parse::<u32>()
-    {
-        Ok(freq) => freq,
-        Err(_) => {
-            eprintln!("Failed to parse scaling_setspeed");
-            return None;
-        }
-    };
-
-    match std::fs::write(
-        format!(
-            "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_setspeed",
-            core
-        ),
-        new_freq.to_string(),
-    ) {
-        Ok(_) => return Some(old_freq),
-        Err(_) => {
-            eprintln!("Failed to write scaling_setspeed");
-            return None;
-        }
+const INTEL_PSTATE_NO_TURBO: &str = "/sys/devices/system/cpu/intel_pstate/no_turbo";
+const GENERIC_CPUFREQ_BOOST: &str = "/sys/devices/system/cpu/cpufreq/boost";
+
+/// Disable turbo boost for the duration of the run, returning the old
+/// value so it can be restored with `turbo_restore`. Returns `None` if no
+/// known boost-control interface is present or it could not be written,
+/// in which case the run proceeds without a turbo guarantee.
+fn turbo_set_disabled() -> Option<TurboState> {
+    if PathBuf::from(INTEL_PSTATE_NO_TURBO).exists() {
+        let old_value = std::fs::read_to_string(INTEL_PSTATE_NO_TURBO).ok()?.trim().to_string();
+        return match std::fs::write(INTEL_PSTATE_NO_TURBO, "1") {
+            Ok(_) => Some(TurboState::IntelPstate(old_value)),
+            Err(_) => {
+                eprintln!("Failed to write {}", INTEL_PSTATE_NO_TURBO);
+                None
+            }
+        };
     }
-}
 
-fn cpufreq_available(core: u32, freq: u32) -> bool {
-    let path = PathBuf::from(format!(
-        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_available_frequencies",
-        core
-    ));
-
-    let raw_freqs =
-        std::fs::read_to_string(path).expect("Failed to open scaling_available_frequencies");
-
-    let freqs = raw_freqs
-        .split(" ")
-        .into_iter()
-        .filter(|s| {
-            if s.trim().is_empty() {
-                return false;
-            }
-            return true;
-        })
-        .map(|s| match s.trim().parse::<u32>() {
-            Ok(freq) => freq,
+    if PathBuf::from(GENERIC_CPUFREQ_BOOST).exists() {
+        let old_value = std::fs::read_to_string(GENERIC_CPUFREQ_BOOST).ok()?.trim().to_string();
+        return match std::fs::write(GENERIC_CPUFREQ_BOOST, "0") {
+            Ok(_) => Some(TurboState::GenericBoost(old_value)),
             Err(_) => {
-                eprintln!(
-                    "Failed to parse scaling_available_frequencies, InvalidDigit `{}`",
-                    s
-                );
-                std::process::exit(1);
-            }
-        })
-        .collect::<Vec<u32>>();
-
-    for (i, available_freq) in freqs.iter().enumerate() {
-        let available_freq = *available_freq;
-        if available_freq == freq {
-            if i == 0 {
-                return true;
+                eprintln!("Failed to write {}", GENERIC_CPUFREQ_BOOST);
+                None
             }
+        };
+    }
+
+    None
+}
 
-            let prev_freq = freqs[i - 1];
-            if available_freq - 1000 == prev_freq {
-                eprintln!(
-                    "The frequency {} is not available, using the closest one {}",
-                    freq, prev_freq
-                );
-                return false;
-            } else {
-                return true;
+fn turbo_restore(state: TurboState) {
+    match state {
+        TurboState::IntelPstate(old_value) => {
+            if std::fs::write(INTEL_PSTATE_NO_TURBO, old_value).is_err() {
+                eprintln!("Failed to restore {}", INTEL_PSTATE_NO_TURBO);
+            }
+        }
+        TurboState::GenericBoost(old_value) => {
+            if std::fs::write(GENERIC_CPUFREQ_BOOST, old_value).is_err() {
+                eprintln!("Failed to restore {}", GENERIC_CPUFREQ_BOOST);
             }
         }
     }
-
-    eprintln!("The frequency {} is not available", freq);
-    return false;
 }
 
-fn hr_sleep_bench(micro: time::Duration) -> Vec<u64> {
-    let mut results = Vec::new();
-    for _ in 0..NUM_ITERATIONS {
+fn hr_sleep_bench(
+    micro: time::Duration,
+    min_time: time::Duration,
+    threshold: f64,
+    max_iterations: u32,
+) -> Vec<u64> {
+    adaptive::run(min_time, threshold, max_iterations, || {
         let start = unsafe { core::arch::x86_64::_rdtsc() };
         // hrsleep::hr_sleep(micro);
         std::thread::sleep(micro);
         let end = unsafe { core::arch::x86_64::_rdtsc() };
-        results.push(end - start);
-    }
-    results.sort();
-    results
+        end - start
+    })
 }
 
-fn nano_sleep_bench(micro: time::Duration) -> Vec<u64> {
-    let mut results = Vec::new();
-    for _ in 0..NUM_ITERATIONS {
+fn nano_sleep_bench(
+    micro: time::Duration,
+    min_time: time::Duration,
+    threshold: f64,
+    max_iterations: u32,
+) -> Vec<u64> {
+    adaptive::run(min_time, threshold, max_iterations, || {
         let start = unsafe { core::arch::x86_64::_rdtsc() };
         std::thread::sleep(micro);
         // hrsleep::nanosleep(micro);
         let end = unsafe { core::arch::x86_64::_rdtsc() };
-        results.push(end - start);
-    }
-    results.sort();
-    results
+        end - start
+    })
 }
@@ -0,0 +1,183 @@
+//! Reporting layer: collects per-test-case statistics into a `Report`
+//! and renders it as the original fixed text table, or as JSON/CSV so
+//! runs can be diffed or fed into a dashboard.
+
+use clap::ValueEnum;
+
+use crate::stats::Stats;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+pub struct BenchOptions {
+    pub core: u32,
+    pub freq_mhz: u32,
+    pub turbo_off: bool,
+}
+
+pub struct CaseResult {
+    pub case_micros: u128,
+    pub hr_sleep: Stats,
+    pub nano_sleep: Stats,
+    pub hr_sleep_watts: Option<f64>,
+    pub nano_sleep_watts: Option<f64>,
+    pub hr_sleep_contention_pct: Option<f64>,
+    pub nano_sleep_contention_pct: Option<f64>,
+}
+
+pub struct Report {
+    pub options: BenchOptions,
+    pub cases: Vec<CaseResult>,
+}
+
+impl Report {
+    pub fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Table => self.print_table(),
+            OutputFormat::Json => self.print_json(),
+            OutputFormat::Csv => self.print_csv(),
+        }
+    }
+
+    fn print_table(&self) {
+        println!("Benchmark Options: ");
+        println!("  Core: {}", self.options.core);
+        println!("  Frequency: {} MHz", self.options.freq_mhz);
+        println!(
+            "  Turbo boost: {}",
+            if self.options.turbo_off { "off" } else { "unknown (could not disable)" }
+        );
+        println!("");
+
+        println!("Benchmark result: ");
+        println!("                hr_sleep              nanosleep");
+        for case in &self.cases {
+            println!(
+                "{}µs        {:.2}ns/{:.2}ns     {:.2}ns/{:.2}ns",
+                case.case_micros, case.hr_sleep.mean_ns, case.hr_sleep.p99_ns, case.nano_sleep.mean_ns, case.nano_sleep.p99_ns
+            );
+            println!(
+                "           {}              {}",
+                watts_str(case.hr_sleep_watts),
+                watts_str(case.nano_sleep_watts)
+            );
+            println!(
+                "           {}              {}",
+                contention_str(case.hr_sleep_contention_pct),
+                contention_str(case.nano_sleep_contention_pct)
+            );
+        }
+    }
+
+    fn print_json(&self) {
+        let cases = self
+            .cases
+            .iter()
+            .map(|case| {
+                format!(
+                    "{{\"case_micros\":{},\"hr_sleep\":{},\"nano_sleep\":{},\"hr_sleep_watts\":{},\"nano_sleep_watts\":{},\"hr_sleep_contention_pct\":{},\"nano_sleep_contention_pct\":{}}}",
+                    case.case_micros,
+                    stats_json(&case.hr_sleep),
+                    stats_json(&case.nano_sleep),
+                    opt_f64_json(case.hr_sleep_watts),
+                    opt_f64_json(case.nano_sleep_watts),
+                    opt_f64_json(case.hr_sleep_contention_pct),
+                    opt_f64_json(case.nano_sleep_contention_pct),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        println!(
+            "{{\"options\":{{\"core\":{},\"freq_mhz\":{},\"turbo_off\":{}}},\"cases\":[{}]}}",
+            self.options.core, self.options.freq_mhz, self.options.turbo_off, cases
+        );
+    }
+
+    fn print_csv(&self) {
+        println!(
+            "case_micros,primitive,iterations,min_ns,median_ns,mean_ns,stddev_ns,p50_ns,p90_ns,p99_ns,p999_ns,watts,contention_pct"
+        );
+        for case in &self.cases {
+            print_csv_row(
+                case.case_micros,
+                "hr_sleep",
+                &case.hr_sleep,
+                case.hr_sleep_watts,
+                case.hr_sleep_contention_pct,
+            );
+            print_csv_row(
+                case.case_micros,
+                "nanosleep",
+                &case.nano_sleep,
+                case.nano_sleep_watts,
+                case.nano_sleep_contention_pct,
+            );
+        }
+    }
+}
+
+fn print_csv_row(case_micros: u128, primitive: &str, stats: &Stats, watts: Option<f64>, contention_pct: Option<f64>) {
+    println!(
+        "{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{},{}",
+        case_micros,
+        primitive,
+        stats.iterations,
+        stats.min_ns,
+        stats.median_ns,
+        stats.mean_ns,
+        stats.stddev_ns,
+        stats.p50_ns,
+        stats.p90_ns,
+        stats.p99_ns,
+        stats.p999_ns,
+        match watts {
+            Some(watts) => format!("{:.2}", watts),
+            None => "".to_string(),
+        },
+        match contention_pct {
+            Some(pct) => format!("{:.2}", pct),
+            None => "".to_string(),
+        }
+    );
+}
+
+fn watts_str(watts: Option<f64>) -> String {
+    match watts {
+        Some(watts) => format!("{:.2}W", watts),
+        None => "energy: unavailable".to_string(),
+    }
+}
+
+fn contention_str(contention_pct: Option<f64>) -> String {
+    match contention_pct {
+        Some(pct) => format!("contention: {:.1}%", pct),
+        None => "contention: unavailable".to_string(),
+    }
+}
+
+fn opt_f64_json(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => "null".to_string(),
+    }
+}
+
+fn stats_json(stats: &Stats) -> String {
+    format!(
+        "{{\"iterations\":{},\"min_ns\":{:.2},\"median_ns\":{:.2},\"mean_ns\":{:.2},\"stddev_ns\":{:.2},\"p50_ns\":{:.2},\"p90_ns\":{:.2},\"p99_ns\":{:.2},\"p999_ns\":{:.2}}}",
+        stats.iterations,
+        stats.min_ns,
+        stats.median_ns,
+        stats.mean_ns,
+        stats.stddev_ns,
+        stats.p50_ns,
+        stats.p90_ns,
+        stats.p99_ns,
+        stats.p999_ns,
+    )
+}
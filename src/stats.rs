@@ -0,0 +1,69 @@
+//! Summary statistics over a sorted set of TSC cycle deltas, shared by
+//! the reporter (min/median/mean/stddev/percentiles) and the adaptive
+//! iteration harness (relative standard error of the mean).
+
+fn cycles_to_ns(cycles: f64, tsc_per_micro: f64) -> f64 {
+    cycles / tsc_per_micro * 1000.0
+}
+
+pub fn mean_cycles(samples: &[u64]) -> f64 {
+    samples.iter().sum::<u64>() as f64 / samples.len() as f64
+}
+
+pub fn stddev_cycles(samples: &[u64], mean: f64) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+
+    let variance = samples
+        .iter()
+        .map(|&c| {
+            let d = c as f64 - mean;
+            d * d
+        })
+        .sum::<f64>()
+        / (samples.len() as f64 - 1.0);
+
+    variance.sqrt()
+}
+
+/// Relative standard error of the mean: stddev / (mean * sqrt(n)).
+pub fn relative_standard_error(mean: f64, stddev: f64, n: usize) -> f64 {
+    stddev / (mean * (n as f64).sqrt())
+}
+
+fn percentile_cycles(sorted: &[u64], numerator: usize, denominator: usize) -> u64 {
+    let idx = (sorted.len() * numerator / denominator).min(sorted.len() - 1);
+    sorted[idx]
+}
+
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub iterations: usize,
+    pub min_ns: f64,
+    pub median_ns: f64,
+    pub mean_ns: f64,
+    pub stddev_ns: f64,
+    pub p50_ns: f64,
+    pub p90_ns: f64,
+    pub p99_ns: f64,
+    pub p999_ns: f64,
+}
+
+/// `sorted_cycles` must already be sorted ascending, as `*_bench` produces.
+pub fn compute(sorted_cycles: &[u64], tsc_per_micro: f64) -> Stats {
+    let mean = mean_cycles(sorted_cycles);
+    let stddev = stddev_cycles(sorted_cycles, mean);
+
+    Stats {
+        iterations: sorted_cycles.len(),
+        min_ns: cycles_to_ns(sorted_cycles[0] as f64, tsc_per_micro),
+        median_ns: cycles_to_ns(percentile_cycles(sorted_cycles, 50, 100) as f64, tsc_per_micro),
+        mean_ns: cycles_to_ns(mean, tsc_per_micro),
+        stddev_ns: cycles_to_ns(stddev, tsc_per_micro),
+        p50_ns: cycles_to_ns(percentile_cycles(sorted_cycles, 50, 100) as f64, tsc_per_micro),
+        p90_ns: cycles_to_ns(percentile_cycles(sorted_cycles, 90, 100) as f64, tsc_per_micro),
+        p99_ns: cycles_to_ns(percentile_cycles(sorted_cycles, 99, 100) as f64, tsc_per_micro),
+        p999_ns: cycles_to_ns(percentile_cycles(sorted_cycles, 999, 1000) as f64, tsc_per_micro),
+    }
+}